@@ -0,0 +1,29 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Interpreter a [`ScriptRequest`]'s `script` body should be run with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptRequestLanguage {
+    Bash,
+    /// Windows doesn't ship `bash`, so Windows-targeted scripts are written
+    /// in PowerShell instead.
+    Powershell,
+}
+
+/// What a [`ScriptRequest`] is being run for, so the UI can label it
+/// appropriately.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptContext {
+    ToolInstallScript,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, JsonSchema)]
+pub struct ScriptRequest {
+    pub script: String,
+    pub language: ScriptRequestLanguage,
+    pub context: ScriptContext,
+    pub working_dir: Option<String>,
+}
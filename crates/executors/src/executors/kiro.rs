@@ -1,11 +1,19 @@
-use std::{path::Path, process::Stdio, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 
 use async_trait::async_trait;
 use command_group::AsyncCommandGroup;
 use derivative::Derivative;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tokio::{io::AsyncWriteExt, process::Command};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::Command,
+};
 use ts_rs::TS;
 use workspace_utils::msg_store::MsgStore;
 
@@ -23,6 +31,114 @@ use crate::{
     },
 };
 
+/// Oldest `kiro-cli` version this executor knows how to drive. Versions
+/// below this are treated as [`AvailabilityInfo::OutdatedVersion`] so the
+/// setup flow can force a reinstall instead of silently reusing a stale
+/// binary.
+const MIN_SUPPORTED_KIRO_VERSION: (u64, u64, u64) = (1, 2, 0);
+
+fn format_version((major, minor, patch): (u64, u64, u64)) -> String {
+    format!("{major}.{minor}.{patch}")
+}
+
+/// Parses the first `x.y.z` token out of `kiro-cli --version` output,
+/// ignoring any pre-release/build suffix (e.g. `1.2.0-beta`).
+fn parse_kiro_version(output: &str) -> Option<(u64, u64, u64)> {
+    output.split_whitespace().find_map(|token| {
+        let token = token.trim_start_matches('v');
+        let mut parts = token
+            .split(|c: char| c == '-' || c == '+')
+            .next()?
+            .split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = parts.next()?.parse().ok()?;
+        Some((major, minor, patch))
+    })
+}
+
+/// `.kiro-sessions` subdirectories are never cleaned up by `kiro-cli`
+/// itself, so they accumulate across every task run in a worktree. Anything
+/// older than this is pruned right before a new initial spawn.
+const STALE_SESSION_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Removes session subdirectories under `sessions_root` whose last
+/// modification is older than `ttl`. Best-effort: a directory we fail to
+/// inspect or remove is simply left in place for the next run to retry.
+async fn cleanup_stale_sessions(sessions_root: &Path, ttl: Duration) {
+    let Ok(mut entries) = tokio::fs::read_dir(sessions_root).await else {
+        return;
+    };
+
+    let now = SystemTime::now();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        if !metadata.is_dir() {
+            continue;
+        }
+        let Ok(modified) = metadata.modified() else {
+            continue;
+        };
+        let Ok(age) = now.duration_since(modified) else {
+            continue;
+        };
+        if age > ttl {
+            tracing::info!("Kiro: pruning stale session dir {}", path.display());
+            if let Err(err) = tokio::fs::remove_dir_all(&path).await {
+                tracing::warn!("Kiro: failed to prune stale session dir {}: {err}", path.display());
+            }
+        }
+    }
+}
+
+/// How long we wait for `kiro-cli` to write its own session file inside a
+/// freshly created pending directory before giving up and falling back to a
+/// generated id.
+const SESSION_ID_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(30);
+const SESSION_ID_DISCOVERY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Polls `pending_dir` for the session file `kiro-cli` writes once it starts
+/// a session, returning its file stem as the real session id. `kiro-cli`
+/// only ever writes into this directory for the task that owns it, so the
+/// first file to show up is unambiguously the right one.
+async fn discover_kiro_session_id(pending_dir: &Path) -> Option<String> {
+    discover_kiro_session_id_with_timing(
+        pending_dir,
+        SESSION_ID_DISCOVERY_TIMEOUT,
+        SESSION_ID_DISCOVERY_POLL_INTERVAL,
+    )
+    .await
+}
+
+async fn discover_kiro_session_id_with_timing(
+    pending_dir: &Path,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Option<String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if let Ok(mut entries) = tokio::fs::read_dir(pending_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let is_file = entry.file_type().await.is_ok_and(|t| t.is_file());
+                if !is_file {
+                    continue;
+                }
+                let path = entry.path();
+                if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                    return Some(id.to_string());
+                }
+            }
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
 #[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
 #[derivative(Debug, PartialEq)]
 pub struct Kiro {
@@ -36,6 +152,13 @@ pub struct Kiro {
     #[ts(skip)]
     #[derivative(Debug = "ignore", PartialEq = "ignore")]
     pub approvals: Option<Arc<dyn ExecutorApprovalService>>,
+    /// The per-task pending session directory created by `spawn`, handed off
+    /// to `normalize_logs` so it can discover Kiro's real session id and
+    /// rename the directory to match.
+    #[serde(skip)]
+    #[ts(skip)]
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pending_session_dir: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl Kiro {
@@ -49,6 +172,72 @@ impl Kiro {
 
         apply_overrides(builder, &self.cmd)
     }
+
+    /// Runs `kiro-cli login`, watching its stdout for the device-code
+    /// verification prompt. Once spotted, the URL+code are surfaced through
+    /// [`ExecutorApprovalService`] and we wait for the user to confirm
+    /// they've completed the browser login before treating login as done;
+    /// without an approval service wired up we just wait for the process to
+    /// exit on its own.
+    pub async fn run_login(&self) -> Result<(), ExecutorError> {
+        // `kiro-cli login` persists credentials to the user's `~/.kiro`
+        // config rather than anything workspace-local, so this isn't tied
+        // to any particular task's working directory.
+        let mut command = Command::new("kiro-cli");
+        command
+            .arg("login")
+            .kill_on_drop(true)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = command.spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| std::io::Error::other("kiro-cli login: missing stdout"))?;
+
+        let mut lines = BufReader::new(stdout).lines();
+        let mut prompted = false;
+        while let Some(line) = lines.next_line().await? {
+            tracing::debug!("Kiro login: {line}");
+            if !prompted && line.contains("http") {
+                prompted = true;
+                if let Some(approvals) = &self.approvals {
+                    tracing::info!("Kiro login: awaiting approval for device-code prompt");
+                    if !approvals.request_approval(line.clone()).await {
+                        child.kill().await.ok();
+                        return Err(ExecutorError::from(std::io::Error::other(
+                            "kiro-cli login was not approved by the user",
+                        )));
+                    }
+                }
+            }
+        }
+
+        let status = child.wait().await?;
+        if !status.success() {
+            return Err(ExecutorError::from(std::io::Error::other(format!(
+                "kiro-cli login exited with {status}"
+            ))));
+        }
+        Ok(())
+    }
+
+    /// Cheap authentication probe so callers can skip [`Self::run_login`]
+    /// (and the device-code approval prompt it triggers) when credentials
+    /// are already valid.
+    pub async fn is_authenticated(&self) -> bool {
+        Command::new("kiro-cli")
+            .arg("whoami")
+            .kill_on_drop(true)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .is_ok_and(|status| status.success())
+    }
 }
 
 #[async_trait]
@@ -68,9 +257,18 @@ impl StandardCodingAgentExecutor for Kiro {
         let combined_prompt = self.append_prompt.combine_prompt(prompt);
 
         // Create a unique session directory for this task to avoid session conflicts
-        let session_dir = current_dir.join(".kiro-sessions");
-        std::fs::create_dir_all(&session_dir).ok();
-        
+        let sessions_root = current_dir.join(".kiro-sessions");
+        tokio::fs::create_dir_all(&sessions_root).await.ok();
+        cleanup_stale_sessions(&sessions_root, STALE_SESSION_TTL).await;
+
+        // Kiro doesn't let us name a session up front, so give this task its
+        // own directory before it writes anything into it. Since no other
+        // task will ever spawn into this exact directory, whatever session
+        // file shows up inside it is unambiguously this task's session.
+        let session_dir = sessions_root.join(format!("pending-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&session_dir).await?;
+        *self.pending_session_dir.lock().unwrap() = Some(session_dir.clone());
+
         tracing::info!("Kiro initial: Starting NEW session in {}, prompt length: {} chars", 
                       session_dir.display(), combined_prompt.len());
         tracing::debug!("Kiro initial: Command: {} {:?}", executable_path.display(), args);
@@ -109,15 +307,17 @@ impl StandardCodingAgentExecutor for Kiro {
         session_id: &str,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        // Use --resume flag to continue the most recent session
-        let command_parts = self
-            .build_command_builder()
-            .build_follow_up(&["--resume".to_string()])?;
+        // Resume the specific session this task owns, scoped to its own
+        // session directory, rather than letting kiro-cli guess at "the
+        // most recent session" shared across every task in this worktree.
+        let command_parts = self.build_command_builder().build_follow_up(&[
+            "--resume".to_string(),
+            session_id.to_string(),
+        ])?;
         let (executable_path, args) = command_parts.into_resolved().await?;
-        
-        // Use the same session directory as initial spawn
-        let session_dir = current_dir.join(".kiro-sessions");
-        
+
+        let session_dir = current_dir.join(".kiro-sessions").join(session_id);
+
         let combined_prompt = self.append_prompt.combine_prompt(prompt);
         tracing::info!("Kiro follow-up: RESUMING session in {}, prompt length: {} chars", 
                       session_dir.display(), combined_prompt.len());
@@ -157,10 +357,54 @@ impl StandardCodingAgentExecutor for Kiro {
 
         let entry_index_provider = EntryIndexProvider::start_from(&msg_store);
 
-        // Generate a session ID for this Kiro session and emit it immediately
-        let session_id = uuid::Uuid::new_v4().to_string();
-        msg_store.push_session_id(session_id);
-        tracing::info!("Kiro: Generated session ID for follow-up tracking");
+        // Discover Kiro's own session id (rather than fabricating one) so
+        // that a later follow-up can `--resume` this exact session instead
+        // of whichever one kiro-cli happens to think is "most recent".
+        if let Some(pending_dir) = self.pending_session_dir.lock().unwrap().take() {
+            let msg_store_session = msg_store.clone();
+            tokio::spawn(async move {
+                // Whatever id we push must name a directory that actually
+                // exists on disk, since `spawn_follow_up` joins it straight
+                // onto `.kiro-sessions` and hands it to `Command::current_dir`.
+                // The pending dir's own name is always a safe fallback: it's
+                // unconditionally created up front, so it exists regardless
+                // of whether discovery or the rename below succeeds.
+                let pending_name = pending_dir
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| pending_dir.display().to_string());
+
+                let session_id = match discover_kiro_session_id(&pending_dir).await {
+                    Some(id) => {
+                        let sessions_root = pending_dir
+                            .parent()
+                            .map(Path::to_path_buf)
+                            .unwrap_or_else(|| pending_dir.clone());
+                        let final_dir = sessions_root.join(&id);
+                        match tokio::fs::rename(&pending_dir, &final_dir).await {
+                            Ok(()) => id,
+                            Err(err) => {
+                                tracing::warn!(
+                                    "Kiro: failed to move session dir {} to {}: {err}, keeping {pending_name} as the session id",
+                                    pending_dir.display(),
+                                    final_dir.display()
+                                );
+                                pending_name
+                            }
+                        }
+                    }
+                    None => {
+                        tracing::warn!(
+                            "Kiro: timed out discovering session id in {}, keeping {pending_name} as the session id",
+                            pending_dir.display()
+                        );
+                        pending_name
+                    }
+                };
+                msg_store_session.push_session_id(session_id);
+            });
+        }
 
         // Process stdout as plain text
         let msg_store_stdout = msg_store.clone();
@@ -220,11 +464,125 @@ impl StandardCodingAgentExecutor for Kiro {
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
-        // Check if kiro-cli is installed by looking for the binary
-        if which::which("kiro-cli").is_ok() {
-            AvailabilityInfo::InstallationFound
-        } else {
-            AvailabilityInfo::NotFound
+        let Ok(path) = which::which("kiro-cli") else {
+            return AvailabilityInfo::NotFound;
+        };
+
+        // `kiro-cli` never prompts about its own staleness, so the only way
+        // to catch a too-old binary is to parse its reported version here
+        // and compare it against what this executor requires.
+        let Ok(output) = std::process::Command::new(&path).arg("--version").output() else {
+            return AvailabilityInfo::InstallationFound;
+        };
+        if !output.status.success() {
+            return AvailabilityInfo::InstallationFound;
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match parse_kiro_version(&stdout) {
+            Some(found) if found < MIN_SUPPORTED_KIRO_VERSION => {
+                AvailabilityInfo::OutdatedVersion {
+                    found: format_version(found),
+                    required: format_version(MIN_SUPPORTED_KIRO_VERSION),
+                }
+            }
+            _ => AvailabilityInfo::InstallationFound,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use filetime::FileTime;
+
+    use super::*;
+
+    #[test]
+    fn parse_kiro_version_plain_semver() {
+        assert_eq!(parse_kiro_version("kiro-cli 1.2.3"), Some((1, 2, 3)));
+    }
+
+    #[test]
+    fn parse_kiro_version_with_leading_v() {
+        assert_eq!(parse_kiro_version("v1.2.3"), Some((1, 2, 3)));
+    }
+
+    #[test]
+    fn parse_kiro_version_ignores_prerelease_suffix() {
+        assert_eq!(parse_kiro_version("1.2.3-beta.1"), Some((1, 2, 3)));
+        assert_eq!(parse_kiro_version("1.2.3+build.7"), Some((1, 2, 3)));
+    }
+
+    #[test]
+    fn parse_kiro_version_picks_first_version_like_token() {
+        assert_eq!(
+            parse_kiro_version("kiro-cli version 1.2.3 (commit abc123)"),
+            Some((1, 2, 3))
+        );
+    }
+
+    #[test]
+    fn parse_kiro_version_malformed_input_is_none() {
+        assert_eq!(parse_kiro_version("kiro-cli"), None);
+        assert_eq!(parse_kiro_version(""), None);
+        assert_eq!(parse_kiro_version("version one point two"), None);
+    }
+
+    #[test]
+    fn format_version_roundtrips() {
+        assert_eq!(format_version((1, 2, 3)), "1.2.3");
+        assert_eq!(parse_kiro_version(&format_version((1, 2, 3))), Some((1, 2, 3)));
+    }
+
+    #[tokio::test]
+    async fn cleanup_stale_sessions_removes_old_dirs_and_keeps_fresh_ones() {
+        let sessions_root = tempfile::tempdir().unwrap();
+        let stale_dir = sessions_root.path().join("stale-session");
+        let fresh_dir = sessions_root.path().join("fresh-session");
+        tokio::fs::create_dir(&stale_dir).await.unwrap();
+        tokio::fs::create_dir(&fresh_dir).await.unwrap();
+
+        let stale_mtime = FileTime::from_system_time(SystemTime::now() - Duration::from_secs(60));
+        filetime::set_file_mtime(&stale_dir, stale_mtime).unwrap();
+
+        cleanup_stale_sessions(sessions_root.path(), Duration::from_secs(30)).await;
+
+        assert!(!stale_dir.exists());
+        assert!(fresh_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn discover_kiro_session_id_finds_session_file() {
+        let pending_dir = tempfile::tempdir().unwrap();
+        let write_dir = pending_dir.path().to_path_buf();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            tokio::fs::write(write_dir.join("abc-123.json"), b"{}")
+                .await
+                .unwrap();
+        });
+
+        let id = discover_kiro_session_id_with_timing(
+            pending_dir.path(),
+            Duration::from_secs(1),
+            Duration::from_millis(10),
+        )
+        .await;
+
+        assert_eq!(id.as_deref(), Some("abc-123"));
+    }
+
+    #[tokio::test]
+    async fn discover_kiro_session_id_times_out_when_nothing_appears() {
+        let pending_dir = tempfile::tempdir().unwrap();
+
+        let id = discover_kiro_session_id_with_timing(
+            pending_dir.path(),
+            Duration::from_millis(50),
+            Duration::from_millis(10),
+        )
+        .await;
+
+        assert_eq!(id, None);
+    }
+}
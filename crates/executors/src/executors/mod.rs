@@ -0,0 +1,17 @@
+pub mod kiro;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Whether a coding agent's CLI is ready to run, as reported by its
+/// executor's `get_availability_info`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AvailabilityInfo {
+    InstallationFound,
+    NotFound,
+    /// The CLI is installed but older than the executor's minimum
+    /// supported version.
+    OutdatedVersion { found: String, required: String },
+}
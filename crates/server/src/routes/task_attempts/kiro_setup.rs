@@ -1,27 +1,43 @@
+use std::{
+    collections::HashSet,
+    sync::{Mutex, OnceLock},
+};
+
 use db::models::{
     execution_process::{ExecutionProcess, ExecutionProcessRunReason},
     session::{CreateSession, Session},
     workspace::{Workspace, WorkspaceError},
 };
 use deployment::Deployment;
-use executors::actions::ExecutorAction;
-#[cfg(unix)]
 use executors::{
     actions::{
-        ExecutorActionType,
+        ExecutorAction, ExecutorActionType,
         script::{ScriptContext, ScriptRequest, ScriptRequestLanguage},
     },
-    executors::kiro::Kiro,
+    executors::{AvailabilityInfo, kiro::Kiro},
 };
 use services::services::container::ContainerService;
 use uuid::Uuid;
 
 use crate::error::ApiError;
 
+/// How long (and how often) to wait for the install script kicked off by
+/// this setup run to land `kiro-cli` on PATH before attempting login.
+const LOGIN_READY_POLL_ATTEMPTS: u32 = 60;
+const LOGIN_READY_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Workspaces with a login poll loop currently in flight, so a second
+/// `run_kiro_setup` call for the same workspace (e.g. two task attempts
+/// created close together) doesn't spawn a competing `kiro-cli login`.
+fn logins_in_progress() -> &'static Mutex<HashSet<Uuid>> {
+    static LOGINS_IN_PROGRESS: OnceLock<Mutex<HashSet<Uuid>>> = OnceLock::new();
+    LOGINS_IN_PROGRESS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
 pub async fn run_kiro_setup(
     deployment: &crate::DeploymentImpl,
     workspace: &Workspace,
-    _kiro: &Kiro,
+    kiro: &Kiro,
 ) -> Result<ExecutionProcess, ApiError> {
     let latest_process = ExecutionProcess::find_latest_by_workspace_and_run_reason(
         &deployment.db().pool,
@@ -34,11 +50,11 @@ pub async fn run_kiro_setup(
         let latest_action = latest_process
             .executor_action()
             .map_err(|e| ApiError::Workspace(WorkspaceError::ValidationError(e.to_string())))?;
-        get_setup_helper_action()
+        get_setup_helper_action(kiro)
             .await?
             .append_action(latest_action.to_owned())
     } else {
-        get_setup_helper_action().await?
+        get_setup_helper_action(kiro).await?
     };
     deployment
         .container()
@@ -71,41 +87,150 @@ pub async fn run_kiro_setup(
     )
     .await?;
 
+    // Drive the interactive login once the install script has had a chance
+    // to land `kiro-cli` on PATH. `Kiro::run_login` is what actually streams
+    // the device-code prompt through the approval service wired up on
+    // `kiro`, rather than leaving it for the user to run by hand. Skipped
+    // entirely if a login for this workspace is already in flight, and
+    // again once `kiro-cli` is up if we're already authenticated.
+    let workspace_id = workspace.id;
+    if logins_in_progress().lock().unwrap().insert(workspace_id) {
+        let login_kiro = kiro.clone();
+        tokio::spawn(async move {
+            let mut installed = false;
+            for _ in 0..LOGIN_READY_POLL_ATTEMPTS {
+                let probe_kiro = login_kiro.clone();
+                let availability =
+                    tokio::task::spawn_blocking(move || probe_kiro.get_availability_info())
+                        .await
+                        .unwrap_or(AvailabilityInfo::NotFound);
+                if matches!(availability, AvailabilityInfo::InstallationFound) {
+                    installed = true;
+                    break;
+                }
+                tokio::time::sleep(LOGIN_READY_POLL_INTERVAL).await;
+            }
+
+            if !installed {
+                tracing::warn!(
+                    "Kiro: gave up waiting for install to finish before attempting login"
+                );
+            } else if login_kiro.is_authenticated().await {
+                tracing::debug!("Kiro: already authenticated, skipping login");
+            } else if let Err(err) = login_kiro.run_login().await {
+                tracing::warn!("Kiro: login failed: {err}");
+            }
+
+            logins_in_progress().lock().unwrap().remove(&workspace_id);
+        });
+    }
+
     Ok(execution_process)
 }
 
-#[cfg(unix)]
-async fn get_setup_helper_action() -> Result<ExecutorAction, ApiError> {
-    // Install script only - login disabled for now
-    let install_script = r#"#!/bin/bash
-set -e
-echo "Installing Kiro CLI..."
-if ! command -v kiro-cli &> /dev/null; then
-    curl -fsSL https://cli.kiro.dev/install | bash
-    echo "Kiro CLI installed successfully"
-else
-    echo "Kiro CLI already installed"
-fi
-echo "Note: Please run 'kiro-cli login' manually to authenticate"
-"#;
+async fn get_setup_helper_action(kiro: &Kiro) -> Result<ExecutorAction, ApiError> {
+    let outdated = match kiro.get_availability_info() {
+        AvailabilityInfo::OutdatedVersion { found, required } => Some((found, required)),
+        _ => None,
+    };
+
+    let os_info = os_info::get();
+    let (script, language) = match os_info.os_type() {
+        os_info::Type::Windows => {
+            (windows_install_script(&outdated), ScriptRequestLanguage::Powershell)
+        }
+        os_info::Type::Macos if which::which("brew").is_ok() => {
+            (macos_brew_install_script(&outdated), ScriptRequestLanguage::Bash)
+        }
+        _ => (unix_curl_install_script(&outdated), ScriptRequestLanguage::Bash),
+    };
 
     let install_request = ScriptRequest {
-        script: install_script.to_string(),
-        language: ScriptRequestLanguage::Bash,
+        script,
+        language,
         context: ScriptContext::ToolInstallScript,
         working_dir: None,
     };
 
-    // Only install, no login
+    // Login is driven separately by `Kiro::run_login` once this install
+    // finishes, so it can stream the device-code prompt through the
+    // approval service instead of running as an opaque background script.
     Ok(ExecutorAction::new(
         ExecutorActionType::ScriptRequest(install_request),
         None,
     ))
 }
 
-#[cfg(not(unix))]
-async fn get_setup_helper_action() -> Result<ExecutorAction, ApiError> {
-    Err(ApiError::Executor(
-        executors::executors::ExecutorError::UnsupportedPlatform,
-    ))
+/// `outdated` is `Some` only when [`Kiro::get_availability_info`] already
+/// found a too-old `kiro-cli` on PATH, so that branch always reinstalls;
+/// otherwise we only install if the binary is missing entirely.
+fn unix_curl_install_script(outdated: &Option<(String, String)>) -> String {
+    if let Some((found, required)) = outdated {
+        format!(
+            r#"#!/bin/bash
+set -e
+echo "Kiro CLI {found} is older than the required {required}; reinstalling..."
+curl -fsSL https://cli.kiro.dev/install | bash
+echo "Kiro CLI upgraded successfully"
+"#
+        )
+    } else {
+        r#"#!/bin/bash
+set -e
+echo "Installing Kiro CLI..."
+if ! command -v kiro-cli &> /dev/null; then
+    curl -fsSL https://cli.kiro.dev/install | bash
+    echo "Kiro CLI installed successfully"
+else
+    echo "Kiro CLI already installed"
+fi
+"#
+        .to_string()
+    }
+}
+
+fn macos_brew_install_script(outdated: &Option<(String, String)>) -> String {
+    if let Some((found, required)) = outdated {
+        format!(
+            r#"#!/bin/bash
+set -e
+echo "Kiro CLI {found} is older than the required {required}; upgrading via Homebrew..."
+brew upgrade kiro-cli || brew install kiro-cli
+echo "Kiro CLI upgraded successfully"
+"#
+        )
+    } else {
+        r#"#!/bin/bash
+set -e
+echo "Installing Kiro CLI..."
+if ! command -v kiro-cli &> /dev/null; then
+    brew install kiro-cli
+    echo "Kiro CLI installed successfully"
+else
+    echo "Kiro CLI already installed"
+fi
+"#
+        .to_string()
+    }
+}
+
+fn windows_install_script(outdated: &Option<(String, String)>) -> String {
+    if let Some((found, required)) = outdated {
+        format!(
+            r#"Write-Host "Kiro CLI {found} is older than the required {required}; reinstalling..."
+iwr https://cli.kiro.dev/install.ps1 -useb | iex
+Write-Host "Kiro CLI upgraded successfully"
+"#
+        )
+    } else {
+        r#"if (-not (Get-Command kiro-cli -ErrorAction SilentlyContinue)) {
+    Write-Host "Installing Kiro CLI..."
+    iwr https://cli.kiro.dev/install.ps1 -useb | iex
+    Write-Host "Kiro CLI installed successfully"
+} else {
+    Write-Host "Kiro CLI already installed"
+}
+"#
+        .to_string()
+    }
 }